@@ -1,517 +1,807 @@
+use rust_decimal::Decimal;
 use serde::{Deserialize, Serialize, Serializer};
+use std::collections::{BTreeMap, HashMap};
+use std::convert::TryFrom;
 use std::error::Error;
+use std::fmt;
+use std::fs::File;
 use std::{env, process};
 
+/// Raw shape of a CSV row, deserialized as-is before being validated into a `Transaction`.
 #[derive(Deserialize, Debug, Clone)]
-struct Transaction {
-    #[serde(rename(deserialize = "type"))]
+struct TransactionRecord {
+    #[serde(rename = "type")]
     tx_type: String,
-    #[serde(rename(deserialize = "client"))]
-    client_id: u16,
-    #[serde(rename(deserialize = "tx"))]
-    tx_id: u32,
-    amount: Option<f64>,
-    #[serde(skip_deserializing)]
-    disputed: bool,
+    client: u16,
+    tx: u32,
+    amount: Option<Decimal>,
 }
 
-impl Transaction {
-    fn amount(&self) -> f64 {
-        self.amount.unwrap_or(0.0)
-    }
+/// A row that looked like a transaction but failed validation while being
+/// turned into one, e.g. a deposit with no amount or an unrecognised type.
+#[derive(Debug)]
+enum ParseError {
+    MissingAmount,
+    NonPositiveAmount,
+    UnexpectedAmount,
+    UnknownType(String),
 }
 
-#[derive(Serialize, Debug)]
-struct Client {
-    #[serde(rename(serialize = "client"))]
-    client_id: u16,
-    #[serde(rename(serialize = "available"))]
-    #[serde(serialize_with = "float_precission")]
-    available_funds: f64,
-    #[serde(rename(serialize = "held"))]
-    #[serde(serialize_with = "float_precission")]
-    held_funds: f64,
-    #[serde(rename(serialize = "total"))]
-    #[serde(serialize_with = "float_precission")]
-    total_funds: f64,
-    #[serde(rename(serialize = "locked"))]
-    locked: bool,
-    #[serde(skip_serializing)]
-    transactions: Vec<Transaction>,
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseError::MissingAmount => write!(f, "missing amount for deposit/withdrawal"),
+            ParseError::NonPositiveAmount => {
+                write!(f, "deposit/withdrawal amount must be positive")
+            }
+            ParseError::UnexpectedAmount => {
+                write!(f, "dispute/resolve/chargeback must not carry an amount")
+            }
+            ParseError::UnknownType(t) => write!(f, "unknown transaction type '{}'", t),
+        }
+    }
 }
 
-fn float_precission<S>(x: &f64, s: S) -> Result<S::Ok, S::Error>
-where
-    S: Serializer,
-{
-    s.serialize_str(&format!("{:.4}", x))
+impl Error for ParseError {}
+
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(try_from = "TransactionRecord")]
+enum Transaction {
+    Deposit {
+        client: u16,
+        tx: u32,
+        amount: Decimal,
+    },
+    Withdrawal {
+        client: u16,
+        tx: u32,
+        amount: Decimal,
+    },
+    Dispute {
+        client: u16,
+        tx: u32,
+    },
+    Resolve {
+        client: u16,
+        tx: u32,
+    },
+    Chargeback {
+        client: u16,
+        tx: u32,
+    },
 }
 
-fn read_input_file(path: &str) -> Result<Vec<Transaction>, Box<dyn Error>> {
-    let mut txs = Vec::new();
+impl TryFrom<TransactionRecord> for Transaction {
+    type Error = ParseError;
 
-    //trim all whitespace
-    let mut rdr = csv::ReaderBuilder::new()
-        .trim(csv::Trim::All)
-        .flexible(true)
-        .from_path(path)?;
-    for result in rdr.deserialize() {
-        // The iterator yields Result<StringRecord, Error>, so we check the
-        // error here.
-        let record: Transaction = result?;
-        txs.push(record);
+    fn try_from(record: TransactionRecord) -> Result<Self, Self::Error> {
+        match record.tx_type.as_str() {
+            "deposit" | "withdrawal" => {
+                let amount = record.amount.ok_or(ParseError::MissingAmount)?;
+                if amount <= Decimal::ZERO {
+                    return Err(ParseError::NonPositiveAmount);
+                }
+                if record.tx_type == "deposit" {
+                    Ok(Transaction::Deposit {
+                        client: record.client,
+                        tx: record.tx,
+                        amount,
+                    })
+                } else {
+                    Ok(Transaction::Withdrawal {
+                        client: record.client,
+                        tx: record.tx,
+                        amount,
+                    })
+                }
+            }
+            "dispute" | "resolve" | "chargeback" => {
+                if record.amount.is_some() {
+                    return Err(ParseError::UnexpectedAmount);
+                }
+                match record.tx_type.as_str() {
+                    "dispute" => Ok(Transaction::Dispute {
+                        client: record.client,
+                        tx: record.tx,
+                    }),
+                    "resolve" => Ok(Transaction::Resolve {
+                        client: record.client,
+                        tx: record.tx,
+                    }),
+                    _ => Ok(Transaction::Chargeback {
+                        client: record.client,
+                        tx: record.tx,
+                    }),
+                }
+            }
+            other => Err(ParseError::UnknownType(other.to_string())),
+        }
     }
-    Ok(txs)
 }
 
-fn deposit(clients: &mut Vec<Client>, transaction: &Transaction) {
-    match find_client(clients, transaction) {
-        Some(mut c) => {
-            c.available_funds += transaction.amount();
-            c.total_funds += transaction.amount();
-            c.transactions.push(transaction.clone());
-        }
-        None => clients.push(Client {
-            client_id: transaction.client_id,
-            available_funds: transaction.amount(),
-            held_funds: 0.0,
-            total_funds: transaction.amount(),
-            locked: false,
-            transactions: vec![transaction.clone()],
-        }),
-    }
+/// Per-client balances, keyed by `client_id` in the `Ledger`.
+#[derive(Debug, Default, Clone)]
+struct AccountInfo {
+    available_funds: Decimal,
+    held_funds: Decimal,
+    locked: bool,
+}
+
+/// A transaction's position in its dispute lifecycle. The only legal
+/// transitions are `Processed -> Disputed`, `Disputed -> Resolved`, and
+/// `Disputed -> ChargedBack`; every other combination is rejected so a
+/// transaction can never be disputed, resolved, or charged back twice.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum TxState {
+    Processed,
+    Disputed,
+    Resolved,
+    ChargedBack,
 }
 
-fn find_client<'a>(
-    clients: &'a mut Vec<Client>,
-    transaction: &Transaction,
-) -> Option<&'a mut Client> {
-    clients
-        .iter_mut()
-        .find(|x| x.client_id == transaction.client_id && !x.locked)
+/// Every way a single transaction can be legitimately rejected while still
+/// letting the rest of the stream process normally.
+#[derive(Debug, PartialEq, Eq)]
+enum LedgerError {
+    NotEnoughFunds,
+    UnknownTx(u16, u32),
+    DuplicateTx(u32),
+    AlreadyDisputed,
+    NotDisputed,
+    FrozenAccount,
 }
 
-fn withdrawal(clients: &mut Vec<Client>, transaction: &Transaction) {
-    if let Some(mut c) = find_client(clients, transaction) {
-        if c.available_funds >= transaction.amount() && c.total_funds >= transaction.amount() {
-            c.available_funds -= transaction.amount();
-            c.total_funds -= transaction.amount();
-            c.transactions.push(transaction.clone());
+impl fmt::Display for LedgerError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LedgerError::NotEnoughFunds => write!(f, "not enough available funds"),
+            LedgerError::UnknownTx(client, tx) => {
+                write!(f, "unknown tx {} for client {}", tx, client)
+            }
+            LedgerError::DuplicateTx(tx) => write!(f, "tx {} was already processed", tx),
+            LedgerError::AlreadyDisputed => write!(f, "transaction is already disputed"),
+            LedgerError::NotDisputed => write!(f, "transaction is not currently disputed"),
+            LedgerError::FrozenAccount => write!(f, "account is locked"),
         }
     }
 }
 
-fn dispute(clients: &mut Vec<Client>, transaction: &Transaction) {
-    if let Some(client) = find_client(clients, transaction) {
-        if let Some(t) = client
-            .transactions
-            .iter_mut()
-            .find(|x| x.tx_id == transaction.tx_id)
-        {
-            client.available_funds -= t.amount();
-            client.held_funds += t.amount();
-            t.disputed = true;
+impl Error for LedgerError {}
 
-            client.transactions.push(transaction.clone())
-        }
-    }
+/// The amount and direction of a processed deposit/withdrawal, recorded so
+/// `dispute`/`resolve`/`chargeback` can tell which fund-accounting rule
+/// applies without re-deriving it from the original `Transaction`.
+#[derive(Debug, Clone, Copy)]
+struct TxInfo {
+    amount: Decimal,
+    is_withdrawal: bool,
 }
 
-fn resolve(clients: &mut Vec<Client>, transaction: &Transaction) {
-    if let Some(client) = find_client(clients, transaction) {
-        if let Some(t) = client
-            .transactions
-            .iter_mut()
-            .find(|x| x.tx_id == transaction.tx_id)
-        {
-            if t.disputed {
-                client.held_funds -= t.amount();
-                client.available_funds += t.amount();
+/// Everywhere the ledger's transaction rules need to read or write account
+/// and transaction state, decoupled from where that state actually lives.
+/// `MemStore` backs it with plain HashMaps; a disk- or database-backed store
+/// could implement the same trait for datasets that exceed RAM.
+trait Store {
+    fn get_account(&self, client: u16) -> Option<AccountInfo>;
+    fn upsert_account(&mut self, client: u16, account: AccountInfo);
+    fn record_tx(&mut self, client: u16, tx: u32, info: TxInfo);
+    fn get_tx(&self, client: u16, tx: u32) -> Option<TxInfo>;
+    fn get_tx_state(&self, client: u16, tx: u32) -> Option<TxState>;
+    fn set_tx_state(&mut self, client: u16, tx: u32, state: TxState);
+    /// All accounts, keyed by `client_id`, for reporting.
+    fn accounts(&self) -> Vec<(u16, AccountInfo)>;
+}
 
-                t.disputed = false;
+/// Default in-memory `Store`, backed by HashMaps keyed by client id and by
+/// `(client, tx)` so every lookup the engine needs is O(1).
+#[derive(Debug, Default)]
+struct MemStore {
+    accounts: HashMap<u16, AccountInfo>,
+    transactions: HashMap<(u16, u32), TxInfo>,
+    transaction_state: HashMap<(u16, u32), TxState>,
+}
 
-                client.transactions.push(transaction.clone());
-            }
-        }
+impl Store for MemStore {
+    fn get_account(&self, client: u16) -> Option<AccountInfo> {
+        self.accounts.get(&client).cloned()
     }
-}
 
-fn chargeback(clients: &mut Vec<Client>, transaction: &Transaction) {
-    if let Some(client) = find_client(clients, transaction) {
-        if let Some(t) = client
-            .transactions
-            .iter_mut()
-            .find(|x| x.tx_id == transaction.tx_id)
-        {
-            if t.disputed {
-                if t.tx_type == "deposit" {
-                    client.total_funds -= t.amount();
-                    client.held_funds -= t.amount();
-                } else {
-                    client.total_funds -= -t.amount();
-                    client.held_funds -= t.amount();
-                }
-                t.disputed = false;
+    fn upsert_account(&mut self, client: u16, account: AccountInfo) {
+        self.accounts.insert(client, account);
+    }
 
-                client.transactions.push(transaction.clone());
-                client.locked = true;
-            }
-        }
+    fn record_tx(&mut self, client: u16, tx: u32, info: TxInfo) {
+        self.transactions.insert((client, tx), info);
     }
-}
 
-//Assumption: If a client does not exist in the "Database" of a Bank, the client can not withdraw any money from there.
-//However, the bank will gladly accept the clients money and open up an account for the client.
-//Clients only get added to the client vector if they added money before doing anything else.
-fn handle_transactions(transactions: Vec<Transaction>) -> Vec<Client> {
-    let mut clients: Vec<Client> = Vec::new();
-
-    for t in transactions.iter() {
-        match t.tx_type.as_str() {
-            "deposit" => deposit(&mut clients, t),
-            "withdrawal" => withdrawal(&mut clients, t),
-            "dispute" => dispute(&mut clients, t),
-            "resolve" => resolve(&mut clients, t),
-            "chargeback" => chargeback(&mut clients, t),
-            _ => continue,
-        }
+    fn get_tx(&self, client: u16, tx: u32) -> Option<TxInfo> {
+        self.transactions.get(&(client, tx)).copied()
+    }
+
+    fn get_tx_state(&self, client: u16, tx: u32) -> Option<TxState> {
+        self.transaction_state.get(&(client, tx)).copied()
+    }
+
+    fn set_tx_state(&mut self, client: u16, tx: u32, state: TxState) {
+        self.transaction_state.insert((client, tx), state);
     }
 
-    clients
+    fn accounts(&self) -> Vec<(u16, AccountInfo)> {
+        self.accounts
+            .iter()
+            .map(|(id, account)| (*id, account.clone()))
+            .collect()
+    }
 }
 
-fn main() -> Result<(), Box<dyn Error>> {
-    let args: Vec<String> = env::args().collect();
+/// Applies the deposit/withdrawal/dispute/resolve/chargeback rules against
+/// any `impl Store`, so the transaction rules stay decoupled from where
+/// balances actually live.
+#[derive(Debug)]
+struct Ledger<S: Store> {
+    store: S,
+}
 
-    if args.len() != 2 {
-        println!("Usage: ./csvread input.csv");
-        process::exit(1);
+impl<S: Store> Ledger<S> {
+    fn new(store: S) -> Self {
+        Ledger { store }
     }
 
-    let filename = &args[1];
-    let txs = match read_input_file(filename) {
-        Ok(v) => v,
-        Err(e) => {
-            eprintln!("{}", e);
-            process::exit(1);
+    /// This is the only function that is allowed to create accounts.
+    /// When there is a "deposit" transaction and the account is not found, the account will be created.
+    /// If the account is found and is unlocked, the funds will be added.
+    fn deposit(&mut self, client: u16, tx: u32, amount: Decimal) -> Result<(), LedgerError> {
+        if self.store.get_tx_state(client, tx).is_some() {
+            return Err(LedgerError::DuplicateTx(tx));
         }
-    };
-
-    let clients = handle_transactions(txs);
 
-    let mut wtr = csv::WriterBuilder::new().from_writer(std::io::stdout());
+        let mut account = self.store.get_account(client).unwrap_or_default();
+        if account.locked {
+            return Err(LedgerError::FrozenAccount);
+        }
 
-    for c in clients {
-        wtr.serialize(c)?;
+        account.available_funds += amount;
+        self.store.upsert_account(client, account);
+        self.store.record_tx(
+            client,
+            tx,
+            TxInfo {
+                amount,
+                is_withdrawal: false,
+            },
+        );
+        self.store.set_tx_state(client, tx, TxState::Processed);
+        Ok(())
     }
 
-    wtr.flush()?;
+    /// Handles withdrawals if and only if the account exists and the money in the account is more or equal to the
+    /// amount of the withdrawal. No margin allowed.
+    fn withdrawal(&mut self, client: u16, tx: u32, amount: Decimal) -> Result<(), LedgerError> {
+        if self.store.get_tx_state(client, tx).is_some() {
+            return Err(LedgerError::DuplicateTx(tx));
+        }
 
-    Ok(())
-}
+        // A never-funded account has no available funds either, so report it
+        // the same way as any other insufficient-balance withdrawal rather
+        // than as an unknown tx (it's the account that's missing, not `tx`).
+        let mut account = self
+            .store
+            .get_account(client)
+            .ok_or(LedgerError::NotEnoughFunds)?;
+        if account.locked {
+            return Err(LedgerError::FrozenAccount);
+        }
+        if account.available_funds < amount {
+            return Err(LedgerError::NotEnoughFunds);
+        }
 
-#[test]
-fn test_deposit() {
-    let mut clients: Vec<Client> = Vec::new();
-    let tx = Transaction {
-        tx_type: "deposit".to_string(),
-        client_id: 1,
-        tx_id: 1,
-        amount: Some(1.0),
-        disputed: false,
-    };
+        account.available_funds -= amount;
+        self.store.upsert_account(client, account);
+        self.store.record_tx(
+            client,
+            tx,
+            TxInfo {
+                amount,
+                is_withdrawal: true,
+            },
+        );
+        self.store.set_tx_state(client, tx, TxState::Processed);
+        Ok(())
+    }
 
-    deposit(&mut clients, &tx);
+    /// The clients way to claim that an transaction was errorneous.
+    /// Disputed transactions will be handled via resolving the issue or a chargeback by the client.
+    /// This function also marks the transaction in question as disputed.
+    ///
+    /// Disputing a deposit moves `amount` from available to held, since those
+    /// funds are still sitting in the account. Disputing a withdrawal instead
+    /// only adds `amount` to held, leaving available untouched: the funds
+    /// already left the account when the withdrawal was processed, so there
+    /// is nothing left in available to move out a second time. `total_funds`
+    /// is never stored directly (see `main`'s `ClientRecord` construction) -
+    /// it is always `available_funds + held_funds` by construction, so it
+    /// can't drift out of sync with the two balances that make it up.
+    ///
+    /// Held funds only ever move between `dispute`, `resolve` and
+    /// `chargeback` on a transaction whose amount was already proven
+    /// available or withdrawn, so `held_funds` must never go negative.
+    fn dispute(&mut self, client: u16, tx: u32) -> Result<(), LedgerError> {
+        match self.store.get_tx_state(client, tx) {
+            Some(TxState::Processed) => {}
+            Some(_) => return Err(LedgerError::AlreadyDisputed),
+            None => return Err(LedgerError::UnknownTx(client, tx)),
+        }
+        let info = self.store.get_tx(client, tx).unwrap();
+
+        let mut account = self
+            .store
+            .get_account(client)
+            .ok_or(LedgerError::UnknownTx(client, tx))?;
+        if account.locked {
+            return Err(LedgerError::FrozenAccount);
+        }
 
-    assert_eq!(1.0, clients.first().unwrap().available_funds);
-    assert_eq!(1.0, clients.first().unwrap().total_funds);
+        if !info.is_withdrawal {
+            account.available_funds -= info.amount;
+        }
+        account.held_funds += info.amount;
+        self.store.upsert_account(client, account);
+        self.store.set_tx_state(client, tx, TxState::Disputed);
+        Ok(())
+    }
 
-    deposit(&mut clients, &tx);
-    assert_eq!(2.0, clients.first().unwrap().available_funds);
-    assert_eq!(2.0, clients.first().unwrap().total_funds);
-    assert_eq!(1, clients.len())
-}
+    /// A disputed transaction gets resolved and the held funds will be given back and are again usable
+    /// for the client. If the transaction is not marked as disputed, the call is rejected with
+    /// `LedgerError::NotDisputed` and the balances are left untouched.
+    ///
+    /// This is the exact inverse of `dispute`: a resolved deposit gives the
+    /// held amount back to available, while a resolved withdrawal just removes
+    /// the amount from held again, since available was never touched in the first place.
+    fn resolve(&mut self, client: u16, tx: u32) -> Result<(), LedgerError> {
+        match self.store.get_tx_state(client, tx) {
+            Some(TxState::Disputed) => {}
+            Some(_) => return Err(LedgerError::NotDisputed),
+            None => return Err(LedgerError::UnknownTx(client, tx)),
+        }
+        let info = self.store.get_tx(client, tx).unwrap();
+
+        let mut account = self
+            .store
+            .get_account(client)
+            .ok_or(LedgerError::UnknownTx(client, tx))?;
+        account.held_funds -= info.amount;
+        if !info.is_withdrawal {
+            account.available_funds += info.amount;
+        }
+        self.store.upsert_account(client, account);
+        self.store.set_tx_state(client, tx, TxState::Resolved);
+        Ok(())
+    }
 
-#[test]
-fn test_withdrawal() {
-    let mut clients: Vec<Client> = Vec::new();
-    let tx_d = Transaction {
-        tx_type: "deposit".to_string(),
-        client_id: 1,
-        tx_id: 1,
-        amount: Some(1.0),
-        disputed: false,
-    };
+    /// Client reverses a transaction. This will immediately freeze (lock) the client.
+    /// If the transaction is not marked as disputed, the call is rejected with
+    /// `LedgerError::NotDisputed` and the balances are left untouched.
+    ///
+    /// A charged-back deposit is confirmed fraudulent: the held amount is
+    /// simply dropped. A charged-back withdrawal is confirmed erroneous and
+    /// gets reversed instead: the withdrawn amount is credited back to
+    /// available, undoing the original withdrawal.
+    fn chargeback(&mut self, client: u16, tx: u32) -> Result<(), LedgerError> {
+        match self.store.get_tx_state(client, tx) {
+            Some(TxState::Disputed) => {}
+            Some(_) => return Err(LedgerError::NotDisputed),
+            None => return Err(LedgerError::UnknownTx(client, tx)),
+        }
+        let info = self.store.get_tx(client, tx).unwrap();
+
+        let mut account = self
+            .store
+            .get_account(client)
+            .ok_or(LedgerError::UnknownTx(client, tx))?;
+        account.held_funds -= info.amount;
+        if info.is_withdrawal {
+            account.available_funds += info.amount;
+        }
+        account.locked = true;
+        self.store.upsert_account(client, account);
+        self.store.set_tx_state(client, tx, TxState::ChargedBack);
+        Ok(())
+    }
 
-    let mut tx_w = Transaction {
-        tx_type: "withdrawal".to_string(),
-        client_id: 1,
-        tx_id: 2,
-        amount: Some(0.5),
-        disputed: false,
-    };
+    //Assumption: If a client does not exist in the "Database" of a Bank, the client can not withdraw any money from there.
+    //However, the bank will gladly accept the clients money and open up an account for the client.
+    //Accounts only get added to the ledger if they were funded before doing anything else.
+    //
+    //A single rejected transaction must not abort the rest of the stream, so every
+    //error is reported to stderr with the offending client/tx and processing continues.
+    fn process(&mut self, t: Transaction) {
+        let (client, tx, result) = match t {
+            Transaction::Deposit { client, tx, amount } => {
+                (client, tx, self.deposit(client, tx, amount))
+            }
+            Transaction::Withdrawal { client, tx, amount } => {
+                (client, tx, self.withdrawal(client, tx, amount))
+            }
+            Transaction::Dispute { client, tx } => (client, tx, self.dispute(client, tx)),
+            Transaction::Resolve { client, tx } => (client, tx, self.resolve(client, tx)),
+            Transaction::Chargeback { client, tx } => (client, tx, self.chargeback(client, tx)),
+        };
 
-    //use never funded the account, so there is no account
-    withdrawal(&mut clients, &tx_w);
-    assert!(clients.is_empty());
+        if let Err(e) = result {
+            eprintln!("rejected transaction (client {}, tx {}): {}", client, tx, e);
+        }
+    }
 
-    //account is created, funded and takes out money
-    deposit(&mut clients, &tx_d);
-    withdrawal(&mut clients, &tx_w);
+    /// Returns the accounts ordered by `client_id` so output is deterministic.
+    fn accounts_sorted(&self) -> BTreeMap<u16, AccountInfo> {
+        self.store.accounts().into_iter().collect()
+    }
+}
 
-    assert_eq!(0.5, clients.first().unwrap().available_funds);
-    assert_eq!(0.5, clients.first().unwrap().total_funds);
+#[derive(Serialize, Debug)]
+struct ClientRecord {
+    #[serde(rename(serialize = "client"))]
+    client_id: u16,
+    #[serde(rename(serialize = "available"))]
+    #[serde(serialize_with = "float_precission")]
+    available_funds: Decimal,
+    #[serde(rename(serialize = "held"))]
+    #[serde(serialize_with = "float_precission")]
+    held_funds: Decimal,
+    #[serde(rename(serialize = "total"))]
+    #[serde(serialize_with = "float_precission")]
+    total_funds: Decimal,
+    #[serde(rename(serialize = "locked"))]
+    locked: bool,
+}
 
-    //withdraw more money than the account has
-    tx_w.amount = Some(5.0);
+fn float_precission<S>(x: &Decimal, s: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    s.serialize_str(&format!("{:.4}", x))
+}
 
-    withdrawal(&mut clients, &tx_w);
+/// Feeds every record off `reader` straight into the ledger one at a time, so
+/// memory use stays bounded no matter how large the input is.
+fn process_reader<R: std::io::Read, S: Store>(
+    reader: R,
+    ledger: &mut Ledger<S>,
+) -> Result<(), Box<dyn Error>> {
+    //trim all whitespace
+    let mut rdr = csv::ReaderBuilder::new()
+        .trim(csv::Trim::All)
+        .flexible(true)
+        .from_reader(reader);
 
-    assert_eq!(0.5, clients.first().unwrap().available_funds);
-    assert_eq!(0.5, clients.first().unwrap().total_funds)
+    for result in rdr.deserialize() {
+        // A malformed row (unknown type, missing amount, wrong column
+        // count, ...) surfaces here as a deserialize error and must not
+        // abort the rest of the stream; only a genuine I/O failure reading
+        // `reader` should.
+        let record: Transaction = match result {
+            Ok(record) => record,
+            Err(e) => {
+                if matches!(e.kind(), csv::ErrorKind::Io(_)) {
+                    return Err(Box::new(e));
+                }
+                eprintln!("skipping malformed row: {}", e);
+                continue;
+            }
+        };
+        ledger.process(record);
+    }
+    Ok(())
 }
 
-#[test]
-fn test_withdrawal_resolve() {
-    let mut clients: Vec<Client> = Vec::new();
-    let tx_d = Transaction {
-        tx_type: "deposit".to_string(),
-        client_id: 1,
-        tx_id: 1,
-        amount: Some(10.0),
-        disputed: false,
-    };
+fn main() -> Result<(), Box<dyn Error>> {
+    let args: Vec<String> = env::args().collect();
 
-    let tx_w = Transaction {
-        tx_type: "withdrawal".to_string(),
-        client_id: 1,
-        tx_id: 2,
-        amount: Some(2.0),
-        disputed: false,
-    };
+    if args.len() > 2 {
+        println!("Usage: ./csvread [input.csv]");
+        process::exit(1);
+    }
 
-    let tx_dispute = Transaction {
-        tx_type: "dispute".to_string(),
-        client_id: 1,
-        tx_id: 2,
-        amount: None,
-        disputed: false,
-    };
+    let mut ledger = Ledger::new(MemStore::default());
 
-    let tx_resolve = Transaction {
-        tx_type: "resolve".to_string(),
-        client_id: 1,
-        tx_id: 2,
-        amount: None,
-        disputed: false,
+    let result = match args.get(1) {
+        Some(path) => File::open(path)
+            .map_err(Box::<dyn Error>::from)
+            .and_then(|f| process_reader(f, &mut ledger)),
+        None => process_reader(std::io::stdin(), &mut ledger),
     };
 
-    //create and fund the account
-    deposit(&mut clients, &tx_d);
-
-    //withdraw an amount
-    withdrawal(&mut clients, &tx_w);
+    if let Err(e) = result {
+        eprintln!("{}", e);
+        process::exit(1);
+    }
 
-    //try to resolve a not disputed transaction (nothing should happen)
-    resolve(&mut clients, &tx_resolve);
-    {
-        let c = clients.first().unwrap();
+    let mut wtr = csv::WriterBuilder::new().from_writer(std::io::stdout());
 
-        assert_eq!(8.0, c.available_funds);
-        assert_eq!(0.0, c.held_funds);
-        assert!(!c.transactions.get(1).unwrap().disputed);
+    for (client_id, account) in ledger.accounts_sorted() {
+        wtr.serialize(ClientRecord {
+            client_id,
+            available_funds: account.available_funds,
+            held_funds: account.held_funds,
+            total_funds: account.available_funds + account.held_funds,
+            locked: account.locked,
+        })?;
     }
 
-    //dispute the withdrawal
-    dispute(&mut clients, &tx_dispute);
+    wtr.flush()?;
+
+    Ok(())
+}
 
-    {
-        let c = clients.first().unwrap();
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
 
-        assert_eq!(6.0, c.available_funds);
-        assert_eq!(2.0, c.held_funds);
-        assert_eq!(8.0, c.total_funds);
-        assert!(c.transactions.get(1).unwrap().disputed);
+    #[test]
+    fn test_deposit() {
+        let mut ledger = Ledger::new(MemStore::default());
+
+        ledger.deposit(1, 1, dec!(1.0)).unwrap();
+
+        let account = &ledger.store.accounts[&1];
+        assert_eq!(dec!(1.0), account.available_funds);
+        assert_eq!(dec!(1.0), account.available_funds + account.held_funds);
+
+        ledger.deposit(1, 2, dec!(1.0)).unwrap();
+        let account = &ledger.store.accounts[&1];
+        assert_eq!(dec!(2.0), account.available_funds);
+        assert_eq!(dec!(2.0), account.available_funds + account.held_funds);
+        assert_eq!(1, ledger.store.accounts.len())
     }
 
-    //resolve the disputed transaction
-    resolve(&mut clients, &tx_resolve);
+    #[test]
+    fn test_withdrawal() {
+        let mut ledger = Ledger::new(MemStore::default());
+
+        //user never funded the account, so there is no account
+        assert_eq!(
+            LedgerError::NotEnoughFunds,
+            ledger.withdrawal(1, 2, dec!(0.5)).unwrap_err()
+        );
+        assert!(ledger.store.accounts.is_empty());
+
+        //account is created, funded and takes out money
+        ledger.deposit(1, 1, dec!(1.0)).unwrap();
+        ledger.withdrawal(1, 2, dec!(0.5)).unwrap();
+
+        let account = &ledger.store.accounts[&1];
+        assert_eq!(dec!(0.5), account.available_funds);
+        assert_eq!(dec!(0.5), account.available_funds + account.held_funds);
+
+        //withdraw more money than the account has
+        assert_eq!(
+            LedgerError::NotEnoughFunds,
+            ledger.withdrawal(1, 3, dec!(5.0)).unwrap_err()
+        );
+
+        let account = &ledger.store.accounts[&1];
+        assert_eq!(dec!(0.5), account.available_funds);
+        assert_eq!(dec!(0.5), account.available_funds + account.held_funds)
+    }
 
-    let c = clients.first().unwrap();
-    assert_eq!(8.0, c.available_funds);
-    assert_eq!(0.0, c.held_funds);
-    assert_eq!(8.0, c.total_funds);
+    #[test]
+    fn test_withdrawal_resolve() {
+        let mut ledger = Ledger::new(MemStore::default());
 
-    assert!(!c.transactions.get(1).unwrap().disputed);
-    assert!(!c.locked)
-}
+        //create and fund the account
+        ledger.deposit(1, 1, dec!(10.0)).unwrap();
 
-#[test]
-fn test_withdrawal_chargeback() {
-    let mut clients: Vec<Client> = Vec::new();
-    let tx_d = Transaction {
-        tx_type: "deposit".to_string(),
-        client_id: 1,
-        tx_id: 1,
-        amount: Some(10.0),
-        disputed: false,
-    };
+        //withdraw an amount
+        ledger.withdrawal(1, 2, dec!(2.0)).unwrap();
 
-    let tx_w = Transaction {
-        tx_type: "withdrawal".to_string(),
-        client_id: 1,
-        tx_id: 2,
-        amount: Some(2.0),
-        disputed: false,
-    };
+        //try to resolve a not disputed transaction (nothing should happen)
+        assert_eq!(LedgerError::NotDisputed, ledger.resolve(1, 2).unwrap_err());
+        {
+            let account = &ledger.store.accounts[&1];
+            assert_eq!(dec!(8.0), account.available_funds);
+            assert_eq!(dec!(0.0), account.held_funds);
+        }
 
-    let tx_dispute = Transaction {
-        tx_type: "dispute".to_string(),
-        client_id: 1,
-        tx_id: 2,
-        amount: None,
-        disputed: false,
-    };
+        //dispute the withdrawal: held grows but available is untouched,
+        //since the withdrawn funds already left the account
+        ledger.dispute(1, 2).unwrap();
+        {
+            let account = &ledger.store.accounts[&1];
+            assert_eq!(dec!(8.0), account.available_funds);
+            assert_eq!(dec!(2.0), account.held_funds);
+            assert_eq!(dec!(10.0), account.available_funds + account.held_funds);
+        }
 
-    let tx_chargeback = Transaction {
-        tx_type: "chargeback".to_string(),
-        client_id: 1,
-        tx_id: 2,
-        amount: None,
-        disputed: false,
-    };
+        //resolve the disputed transaction: the account returns to its exact
+        //pre-dispute balances
+        ledger.resolve(1, 2).unwrap();
+
+        let account = &ledger.store.accounts[&1];
+        assert_eq!(dec!(8.0), account.available_funds);
+        assert_eq!(dec!(0.0), account.held_funds);
+        assert_eq!(dec!(8.0), account.available_funds + account.held_funds);
+        assert!(!account.locked)
+    }
+
+    #[test]
+    fn test_disputed_withdrawal_resolve_restores_exact_pre_dispute_balances() {
+        let mut ledger = Ledger::new(MemStore::default());
 
-    //create and fund the account
-    deposit(&mut clients, &tx_d);
+        ledger.deposit(1, 1, dec!(10.0)).unwrap();
+        ledger.withdrawal(1, 2, dec!(3.0)).unwrap();
 
-    //withdraw an amount
-    withdrawal(&mut clients, &tx_w);
+        let pre_dispute = ledger.store.accounts[&1].clone();
 
-    //try to chargeback a not disputed transaction (nothing should happen)
-    chargeback(&mut clients, &tx_chargeback);
-    {
-        let c = clients.first().unwrap();
+        ledger.dispute(1, 2).unwrap();
+        // held funds must never go negative, and disputing a withdrawal must
+        // not pull available funds down a second time
+        assert!(ledger.store.accounts[&1].held_funds >= dec!(0.0));
+        assert_eq!(dec!(7.0), ledger.store.accounts[&1].available_funds);
 
-        assert_eq!(8.0, c.available_funds);
-        assert_eq!(0.0, c.held_funds);
-        assert!(!c.transactions.get(1).unwrap().disputed);
+        ledger.resolve(1, 2).unwrap();
+
+        let post_resolve = ledger.store.accounts[&1].clone();
+        assert_eq!(pre_dispute.available_funds, post_resolve.available_funds);
+        assert_eq!(pre_dispute.held_funds, post_resolve.held_funds);
     }
 
-    //dispute the withdrawal
-    dispute(&mut clients, &tx_dispute);
+    #[test]
+    fn test_withdrawal_chargeback() {
+        let mut ledger = Ledger::new(MemStore::default());
 
-    //client reverses the transaction
-    chargeback(&mut clients, &tx_chargeback);
+        //create and fund the account
+        ledger.deposit(1, 1, dec!(10.0)).unwrap();
 
-    let c = clients.first().unwrap();
-    assert_eq!(0.0, c.held_funds);
-    assert_eq!(10.0, c.total_funds);
-    assert!(c.locked)
-}
+        //withdraw an amount
+        ledger.withdrawal(1, 2, dec!(2.0)).unwrap();
 
-#[test]
-fn test_deposit_chargeback() {
-    let mut clients: Vec<Client> = Vec::new();
-    let tx_d = Transaction {
-        tx_type: "deposit".to_string(),
-        client_id: 1,
-        tx_id: 1,
-        amount: Some(10.0),
-        disputed: false,
-    };
+        //try to chargeback a not disputed transaction (nothing should happen)
+        assert_eq!(
+            LedgerError::NotDisputed,
+            ledger.chargeback(1, 2).unwrap_err()
+        );
+        {
+            let account = &ledger.store.accounts[&1];
+            assert_eq!(dec!(8.0), account.available_funds);
+            assert_eq!(dec!(0.0), account.held_funds);
+        }
 
-    let tx_dd = Transaction {
-        tx_type: "deposit".to_string(),
-        client_id: 1,
-        tx_id: 2,
-        amount: Some(10.0),
-        disputed: false,
-    };
+        //dispute the withdrawal
+        ledger.dispute(1, 2).unwrap();
 
-    let tx_dispute = Transaction {
-        tx_type: "dispute".to_string(),
-        client_id: 1,
-        tx_id: 2,
-        amount: None,
-        disputed: false,
-    };
+        //client reverses the transaction: the withdrawn amount is credited
+        //back, undoing the withdrawal entirely
+        ledger.chargeback(1, 2).unwrap();
 
-    let tx_chargeback = Transaction {
-        tx_type: "chargeback".to_string(),
-        client_id: 1,
-        tx_id: 2,
-        amount: None,
-        disputed: false,
-    };
+        let account = &ledger.store.accounts[&1];
+        assert_eq!(dec!(0.0), account.held_funds);
+        assert_eq!(dec!(10.0), account.available_funds);
+        assert_eq!(dec!(10.0), account.available_funds + account.held_funds);
+        assert!(account.locked)
+    }
 
-    deposit(&mut clients, &tx_d);
-    deposit(&mut clients, &tx_dd);
+    #[test]
+    fn test_deposit_chargeback() {
+        let mut ledger = Ledger::new(MemStore::default());
 
-    assert_eq!(20.0, clients.first().unwrap().total_funds);
+        ledger.deposit(1, 1, dec!(10.0)).unwrap();
+        ledger.deposit(1, 2, dec!(10.0)).unwrap();
 
-    dispute(&mut clients, &tx_dispute);
-    chargeback(&mut clients, &tx_chargeback);
+        assert_eq!(
+            dec!(20.0),
+            ledger.store.accounts[&1].available_funds + ledger.store.accounts[&1].held_funds
+        );
 
-    assert_eq!(1, clients.len());
-    {
-        let c = clients.first().unwrap();
+        ledger.dispute(1, 2).unwrap();
+        ledger.chargeback(1, 2).unwrap();
+
+        assert_eq!(1, ledger.store.accounts.len());
+        {
+            let account = &ledger.store.accounts[&1];
+            assert_eq!(dec!(10.0), account.available_funds + account.held_funds);
+            assert!(account.locked)
+        }
 
-        assert_eq!(10.0, c.total_funds);
-        assert!(c.locked)
+        //doing something with a locked account is not possible
+        assert_eq!(
+            LedgerError::FrozenAccount,
+            ledger.deposit(1, 3, dec!(10.0)).unwrap_err()
+        );
+        let account = &ledger.store.accounts[&1];
+        assert_eq!(dec!(10.0), account.available_funds + account.held_funds)
     }
 
-    //doing something with a locked account is not possible
-    deposit(&mut clients, &tx_dd);
-    assert_eq!(10.0, clients.first().unwrap().total_funds)
-}
+    #[test]
+    fn test_deposit_resolve() {
+        let mut ledger = Ledger::new(MemStore::default());
 
+        ledger.deposit(1, 1, dec!(10.0)).unwrap();
+        ledger.deposit(1, 2, dec!(10.0)).unwrap();
 
-#[test]
-fn test_deposit_resolve() {
-    let mut clients: Vec<Client> = Vec::new();
-    let tx_d = Transaction {
-        tx_type: "deposit".to_string(),
-        client_id: 1,
-        tx_id: 1,
-        amount: Some(10.0),
-        disputed: false,
-    };
+        assert_eq!(
+            dec!(20.0),
+            ledger.store.accounts[&1].available_funds + ledger.store.accounts[&1].held_funds
+        );
 
-    let tx_dd = Transaction {
-        tx_type: "deposit".to_string(),
-        client_id: 1,
-        tx_id: 2,
-        amount: Some(10.0),
-        disputed: false,
-    };
+        ledger.dispute(1, 2).unwrap();
+        ledger.resolve(1, 2).unwrap();
 
-    let tx_dispute = Transaction {
-        tx_type: "dispute".to_string(),
-        client_id: 1,
-        tx_id: 2,
-        amount: None,
-        disputed: false,
-    };
+        assert_eq!(1, ledger.store.accounts.len());
+        let account = &ledger.store.accounts[&1];
 
-    let tx_chargeback = Transaction {
-        tx_type: "resolve".to_string(),
-        client_id: 1,
-        tx_id: 2,
-        amount: None,
-        disputed: false,
-    };
+        assert_eq!(dec!(0.0), account.held_funds);
+        assert_eq!(dec!(20.0), account.available_funds + account.held_funds);
+    }
 
-    deposit(&mut clients, &tx_d);
-    deposit(&mut clients, &tx_dd);
+    #[test]
+    fn test_duplicate_tx_id_is_rejected() {
+        let mut ledger = Ledger::new(MemStore::default());
 
-    assert_eq!(20.0, clients.first().unwrap().total_funds);
+        ledger.deposit(1, 1, dec!(10.0)).unwrap();
 
-    dispute(&mut clients, &tx_dispute);
-    resolve(&mut clients, &tx_chargeback);
+        // re-using tx id 1 for a second deposit must not be double-counted.
+        assert_eq!(
+            LedgerError::DuplicateTx(1),
+            ledger.deposit(1, 1, dec!(10.0)).unwrap_err()
+        );
+        assert_eq!(
+            LedgerError::DuplicateTx(1),
+            ledger.withdrawal(1, 1, dec!(1.0)).unwrap_err()
+        );
 
-    assert_eq!(1, clients.len());
-    let c = clients.first().unwrap();
+        let account = &ledger.store.accounts[&1];
+        assert_eq!(dec!(10.0), account.available_funds);
+    }
 
-    assert_eq!(0.0, c.held_funds);
-    assert_eq!(20.0, c.total_funds);
+    #[test]
+    fn test_illegal_transitions_leave_funds_untouched() {
+        let mut ledger = Ledger::new(MemStore::default());
+
+        ledger.deposit(1, 1, dec!(10.0)).unwrap();
+        ledger.dispute(1, 1).unwrap();
+        ledger.chargeback(1, 1).unwrap();
+
+        // transaction is now ChargedBack; re-disputing, resolving or charging
+        // back again must be rejected and must not move funds a second time.
+        assert_eq!(
+            LedgerError::AlreadyDisputed,
+            ledger.dispute(1, 1).unwrap_err()
+        );
+        assert_eq!(LedgerError::NotDisputed, ledger.resolve(1, 1).unwrap_err());
+        assert_eq!(
+            LedgerError::NotDisputed,
+            ledger.chargeback(1, 1).unwrap_err()
+        );
+
+        let account = &ledger.store.accounts[&1];
+        assert_eq!(dec!(0.0), account.available_funds);
+        assert_eq!(dec!(0.0), account.held_funds);
+        assert!(account.locked);
+    }
+
+    #[test]
+    fn test_non_positive_amount_is_rejected_at_parse_time() {
+        let zero = TransactionRecord {
+            tx_type: "deposit".to_string(),
+            client: 1,
+            tx: 1,
+            amount: Some(dec!(0.0)),
+        };
+        assert!(matches!(
+            Transaction::try_from(zero).unwrap_err(),
+            ParseError::NonPositiveAmount
+        ));
+
+        let negative = TransactionRecord {
+            tx_type: "withdrawal".to_string(),
+            client: 1,
+            tx: 2,
+            amount: Some(dec!(-50.0)),
+        };
+        assert!(matches!(
+            Transaction::try_from(negative).unwrap_err(),
+            ParseError::NonPositiveAmount
+        ));
+    }
 }